@@ -7,17 +7,22 @@ use crate::{
 };
 use std::{
     cmp::{max, min},
+    collections::HashMap,
     env,
     fs::File,
     io::{self, BufRead, BufReader},
     sync::mpsc::{sync_channel, SyncSender},
     thread,
 };
+use ropey::Rope;
+use signal_hook::{consts::SIGWINCH, iterator::Signals};
 use termion::{
     color::{self, Bg, Fg},
     event::Key,
     get_tty,
     input::TermRead,
+    style::{Invert, NoInvert},
+    terminal_size,
 };
 
 const DATA_BUFFER_SIZE: usize = 500;
@@ -38,6 +43,15 @@ pub fn run() -> io::Result<()> {
         }
     });
 
+    let resize_tx = tx.clone();
+    let mut signals = Signals::new([SIGWINCH])?;
+    thread::spawn(move || {
+        for _ in &mut signals {
+            let event = terminal_size().map(Event::Resize);
+            resize_tx.send(event).expect("Channel has hung up.");
+        }
+    });
+
     let data_tx = tx.clone();
     thread::spawn(move || {
         let mut lines: Vec<String> = Vec::with_capacity(DATA_BUFFER_SIZE);
@@ -85,27 +99,66 @@ fn get_source() -> io::Result<Box<dyn BufRead + Send>> {
 const STATUS_BAR_HEIGHT: usize = 1;
 const CURSOR_SEARCH_OFFSET: usize = 2;
 
+/// Awaiting the letter that names a mark, after `m` or `` ` ``/`'` was pressed.
+enum PendingMark {
+    Set,
+    Jump,
+}
+
+/// The three classes word motions group characters into.
+#[derive(PartialEq, Eq)]
+enum CharClass {
+    Word,
+    Punct,
+    Space,
+}
+
+/// Classifies `c` for word motions. With `long_word`, only the
+/// whitespace/non-whitespace distinction matters (vim's `W`/`B`/`E`).
+fn char_class(c: char, long_word: bool) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Space
+    } else if long_word || c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punct
+    }
+}
+
 pub enum Event {
     MoreData(Box<[String]>),
     Key(Key),
+    Resize((u16, u16)),
 }
 
 struct State {
-    data: Vec<String>,
+    data: Rope,
     offset: usize,
     term: Term,
     dirty: bool,
     mode: Mode,
+    show_line_numbers: bool,
+    marks: HashMap<char, usize>,
+    pending_mark: Option<PendingMark>,
+    pending_count: Option<usize>,
+    cursor_line: usize,
+    cursor_col: usize,
 }
 
 impl State {
     fn new() -> io::Result<Self> {
         let mut state = State {
-            data: Vec::with_capacity(256),
+            data: Rope::new(),
             offset: 0,
             term: Term::new()?,
             dirty: true,
             mode: Mode::Viewing(None),
+            show_line_numbers: false,
+            marks: HashMap::new(),
+            pending_mark: None,
+            pending_count: None,
+            cursor_line: 0,
+            cursor_col: 0,
         };
 
         state.term.hide_cursor()?;
@@ -122,6 +175,10 @@ impl State {
                 false
             }
             Event::Key(key) => self.handle_key(key),
+            Event::Resize(dimensions) => {
+                self.resize(dimensions);
+                false
+            }
         };
 
         self.draw()?;
@@ -130,10 +187,42 @@ impl State {
     }
 
     fn handle_key(&mut self, key: Key) -> bool {
-        match (&self.mode, &Cmd::from_key(&self.mode, key)) {
+        if let Some(pending) = self.pending_mark.take() {
+            if let Key::Char(letter) = key {
+                match pending {
+                    PendingMark::Set => {
+                        self.marks.insert(letter, self.offset);
+                    }
+                    PendingMark::Jump => {
+                        if let Some(&offset) = self.marks.get(&letter) {
+                            self.update_offset(|_| offset);
+                        }
+                    }
+                }
+            }
+
+            return false;
+        }
+
+        let cmd = Cmd::from_key(&self.mode, key);
+
+        if let Cmd::View(ViewCmd::Digit(d)) = &cmd {
+            self.pending_count = Some(
+                self.pending_count
+                    .unwrap_or(0)
+                    .saturating_mul(10)
+                    .saturating_add(*d),
+            );
+            return false;
+        }
+
+        match (&self.mode, &cmd) {
             (Mode::Viewing(maybe_search_text), Cmd::View(view_cmd)) => match view_cmd {
                 ViewCmd::Quit => return true,
-                ViewCmd::Scroll(dir) => self.scroll(*dir),
+                ViewCmd::Scroll(dir) => {
+                    let count = self.pending_count.take();
+                    self.scroll_with_count(*dir, count);
+                }
                 ViewCmd::StartSearching => self.mode = Mode::Searching("".into()),
                 ViewCmd::NextSearchResult => {
                     if let Some(search_text) = maybe_search_text {
@@ -143,6 +232,18 @@ impl State {
                             .unwrap_or(self.offset);
                     }
                 }
+                ViewCmd::ToggleLineNumbers => {
+                    self.show_line_numbers = !self.show_line_numbers;
+                    self.dirty = true;
+                }
+                ViewCmd::SetMark => self.pending_mark = Some(PendingMark::Set),
+                ViewCmd::JumpMark => self.pending_mark = Some(PendingMark::Jump),
+                ViewCmd::Digit(_) => unreachable!("Digits are handled before this match."),
+                ViewCmd::CursorLeft => self.cursor_left(),
+                ViewCmd::CursorRight => self.cursor_right(),
+                ViewCmd::WordForward(long_word) => self.word_forward(*long_word),
+                ViewCmd::WordBack(long_word) => self.word_back(*long_word),
+                ViewCmd::WordEnd(long_word) => self.word_end(*long_word),
                 ViewCmd::Noop => (),
             },
             (Mode::Searching(search_text), Cmd::Search(search_cmd)) => match search_cmd {
@@ -173,6 +274,8 @@ impl State {
             _ => unreachable!("Got mismatched event for current mode."),
         };
 
+        self.pending_count = None;
+
         false
     }
 
@@ -206,7 +309,7 @@ impl State {
             Mode::Viewing(_) => {
                 let percent: f32 =
                     ((self.offset) as f32) / (max(self.max_offset(), 1) as f32) * 100_f32;
-                format!(" {:3.0}% of {} lines", percent, self.data.len())
+                format!(" {:3.0}% of {} lines", percent, self.line_count())
             }
             Mode::Searching(search_text) => format!("/{}", search_text),
         };
@@ -228,11 +331,15 @@ impl State {
         self.term.move_cursor(1, 1)?;
 
         let height = self.term.height() - STATUS_BAR_HEIGHT;
+        let gutter_width = self.gutter_width();
         let mut line_count = 0;
 
-        for line in self.data.iter().skip(self.offset).take(height) {
-            if let Mode::Viewing(Some(ref search_text)) = self.mode {
-                let highlighted_line = line.replace(
+        for (i, line) in self.data.lines_at(self.offset).take(height).enumerate() {
+            let line = line.to_string();
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            let line = if let Mode::Viewing(Some(ref search_text)) = self.mode {
+                line.replace(
                     search_text,
                     &format!(
                         "{bg}{line}{reset_bg}",
@@ -240,13 +347,26 @@ impl State {
                         bg = Bg(color::LightBlack),
                         reset_bg = Bg(color::Reset)
                     ),
-                );
+                )
+            } else {
+                line.to_string()
+            };
 
-                self.term.write_line(&highlighted_line)?;
+            let on_cursor_line = self.offset + i == self.cursor_line;
+            let line = if on_cursor_line && matches!(self.mode, Mode::Viewing(_)) {
+                Self::highlight_cursor(&line, self.cursor_col)
             } else {
-                self.term.write_line(line)?;
+                line
             };
 
+            let gutter = if self.show_line_numbers {
+                format!("{:>width$} ", self.offset + i + 1, width = gutter_width - 1)
+            } else {
+                String::new()
+            };
+
+            self.term.write_line(&format!("{}{}", gutter, line))?;
+
             line_count += 1;
         }
 
@@ -258,42 +378,128 @@ impl State {
         Ok(())
     }
 
+    /// Wraps the character at `col` in inverted video to represent the
+    /// cursor. If `col` is past the end of `line`, the cursor is drawn on a
+    /// trailing blank.
+    ///
+    /// `line` may already contain ANSI escapes spliced in by search
+    /// highlighting; those are passed through untouched and don't count
+    /// towards `col`, so the two highlights can stack on the same span.
+    fn highlight_cursor(line: &str, col: usize) -> String {
+        let mut result = String::new();
+        let mut real_idx = 0;
+        let mut placed = false;
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c == '\u{1b}' {
+                result.push(c);
+                for escape_char in chars.by_ref() {
+                    result.push(escape_char);
+                    if escape_char == 'm' {
+                        break;
+                    }
+                }
+                continue;
+            }
+
+            if real_idx == col {
+                result.push_str(&format!("{}{}{}", Invert, c, NoInvert));
+                placed = true;
+            } else {
+                result.push(c);
+            }
+
+            real_idx += 1;
+        }
+
+        if !placed {
+            result.push_str(&format!("{}{}{}", Invert, ' ', NoInvert));
+        }
+
+        result
+    }
+
+    /// Width of the line-number gutter, including its trailing separator
+    /// space. `0` when `show_line_numbers` is disabled.
+    fn gutter_width(&self) -> usize {
+        if !self.show_line_numbers {
+            return 0;
+        }
+
+        let line_count = self.line_count();
+        let digits = if line_count == 0 {
+            1
+        } else {
+            (line_count as f64).log10().floor() as usize + 1
+        };
+
+        digits + 1
+    }
+
+    /// Number of lines of text, ignoring the trailing empty line ropey
+    /// reports when the buffer ends in a line terminator.
+    fn line_count(&self) -> usize {
+        let lines = self.data.len_lines();
+
+        if lines > 0 && self.data.line(lines - 1).len_chars() == 0 {
+            lines - 1
+        } else {
+            lines
+        }
+    }
+
     fn max_offset(&self) -> usize {
-        self.data.len().checked_sub(self.term.height()).unwrap_or(0) + STATUS_BAR_HEIGHT
+        self.line_count().saturating_sub(self.term.height()) + STATUS_BAR_HEIGHT
+    }
+
+    /// Updates the terminal's dimensions in response to a resize (e.g. a
+    /// `SIGWINCH`), re-clamping the offset so it stays in bounds.
+    fn resize(&mut self, dimensions: (u16, u16)) {
+        self.term.resize(dimensions);
+        self.update_offset(|offset| offset);
+        self.dirty = true;
     }
 
-    fn scroll(&mut self, dir: Dir) {
+    /// Applies a scroll motion. `dir`'s own count (always `1` from
+    /// `Cmd::from_key`) is used as the default, overridden by any numeric
+    /// prefix the user typed beforehand. `Dir::Bottom` with an explicit
+    /// count jumps to that 1-based line instead of the end of the file.
+    fn scroll_with_count(&mut self, dir: Dir, count: Option<usize>) {
         match dir {
-            Dir::Up(count) => self.scroll_up(count),
-            Dir::Down(count) => self.scroll_down(count),
-            Dir::HalfPageDown => self.scroll_half_down(),
-            Dir::HalfPageUp => self.scroll_half_up(),
+            Dir::Up(default) => self.scroll_up(count.unwrap_or(default)),
+            Dir::Down(default) => self.scroll_down(count.unwrap_or(default)),
+            Dir::HalfPageDown => self.scroll_half_down(count.unwrap_or(1)),
+            Dir::HalfPageUp => self.scroll_half_up(count.unwrap_or(1)),
             Dir::Top => self.update_offset(|_| 0),
-            Dir::Bottom => self.scroll_bottom(),
+            Dir::Bottom => match count {
+                Some(line) => self.update_offset(|_| line.saturating_sub(1)),
+                None => self.scroll_bottom(),
+            },
         }
     }
 
     fn scroll_bottom(&mut self) {
-        let offset = self.data.len();
+        let offset = self.line_count();
         self.update_offset(|_| offset);
     }
 
     fn scroll_down(&mut self, count: usize) {
-        self.update_offset(|offset| offset + count);
+        self.update_offset(|offset| offset.saturating_add(count));
     }
 
     fn scroll_up(&mut self, count: usize) {
-        self.update_offset(|offset| offset.checked_sub(count).unwrap_or(0));
+        self.update_offset(|offset| offset.saturating_sub(count));
     }
 
-    fn scroll_half_up(&mut self) {
+    fn scroll_half_up(&mut self, count: usize) {
         let height = self.term.height();
-        self.update_offset(|offset| offset.checked_sub(height / 2).unwrap_or(0));
+        self.update_offset(|offset| offset.saturating_sub((height / 2).saturating_mul(count)));
     }
 
-    fn scroll_half_down(&mut self) {
+    fn scroll_half_down(&mut self, count: usize) {
         let height = self.term.height();
-        self.update_offset(|offset| offset + (height / 2));
+        self.update_offset(|offset| offset.saturating_add((height / 2).saturating_mul(count)));
     }
 
     /// Updates the offset, and ensures it stays within the bounds of the screen.
@@ -303,21 +509,161 @@ impl State {
         if offset != self.offset {
             self.offset = offset;
             self.dirty = true;
+            self.clamp_cursor_to_view();
         }
     }
 
+    /// Pulls the cursor back into the visible range after the offset moves
+    /// out from under it, so scrolling and cursor motions stay in sync.
+    fn clamp_cursor_to_view(&mut self) {
+        let height = self.term.height() - STATUS_BAR_HEIGHT;
+        let max_line = self.data.len_lines().saturating_sub(1);
+
+        let first_visible = self.offset.min(max_line);
+        let last_visible = (self.offset + height.saturating_sub(1))
+            .min(max_line)
+            .max(first_visible);
+
+        self.cursor_line = self.cursor_line.clamp(first_visible, last_visible);
+
+        let line_len = self.data.line(self.cursor_line).len_chars();
+        self.cursor_col = self.cursor_col.min(line_len.saturating_sub(1));
+    }
+
+    fn cursor_char_idx(&self) -> usize {
+        self.data.line_to_char(self.cursor_line) + self.cursor_col
+    }
+
+    /// Moves the cursor to the char at `idx`, auto-scrolling the view so it
+    /// stays visible and marking the screen dirty.
+    fn set_cursor(&mut self, idx: usize) {
+        let idx = idx.min(self.data.len_chars().saturating_sub(1));
+        let line = self.data.char_to_line(idx);
+
+        self.cursor_line = line;
+        self.cursor_col = idx - self.data.line_to_char(line);
+
+        let height = self.term.height() - STATUS_BAR_HEIGHT;
+        if self.cursor_line < self.offset {
+            self.offset = self.cursor_line;
+        } else if self.cursor_line >= self.offset + height {
+            self.offset = self.cursor_line + 1 - height;
+        }
+
+        self.dirty = true;
+    }
+
+    fn cursor_left(&mut self) {
+        let idx = self.cursor_char_idx();
+        if idx == 0 {
+            return;
+        }
+
+        let mut idx = idx - 1;
+        while idx > 0 && self.data.char(idx) == '\n' {
+            idx -= 1;
+        }
+
+        self.set_cursor(idx);
+    }
+
+    fn cursor_right(&mut self) {
+        let len = self.data.len_chars();
+        let mut idx = self.cursor_char_idx() + 1;
+        while idx < len && self.data.char(idx) == '\n' {
+            idx += 1;
+        }
+
+        if idx < len {
+            self.set_cursor(idx);
+        }
+    }
+
+    /// Advances past the remainder of the current word/punctuation/whitespace
+    /// run, then past any following whitespace, landing on the start of the
+    /// next non-whitespace run.
+    fn word_forward(&mut self, long_word: bool) {
+        let len = self.data.len_chars();
+        let mut idx = self.cursor_char_idx();
+        if idx >= len {
+            return;
+        }
+
+        let start_class = char_class(self.data.char(idx), long_word);
+        while idx < len && char_class(self.data.char(idx), long_word) == start_class {
+            idx += 1;
+        }
+        while idx < len && char_class(self.data.char(idx), long_word) == CharClass::Space {
+            idx += 1;
+        }
+
+        self.set_cursor(idx.min(len.saturating_sub(1)));
+    }
+
+    /// Moves backward to the start of the previous non-whitespace run.
+    fn word_back(&mut self, long_word: bool) {
+        let mut idx = self.cursor_char_idx();
+        if idx == 0 {
+            return;
+        }
+
+        idx -= 1;
+        while idx > 0 && char_class(self.data.char(idx), long_word) == CharClass::Space {
+            idx -= 1;
+        }
+
+        if idx > 0 {
+            let class = char_class(self.data.char(idx), long_word);
+            while idx > 0 && char_class(self.data.char(idx - 1), long_word) == class {
+                idx -= 1;
+            }
+        }
+
+        self.set_cursor(idx);
+    }
+
+    /// Moves forward to the last char of the next non-whitespace run.
+    fn word_end(&mut self, long_word: bool) {
+        let len = self.data.len_chars();
+        let mut idx = self.cursor_char_idx();
+        if idx + 1 >= len {
+            return;
+        }
+
+        idx += 1;
+        while idx < len && char_class(self.data.char(idx), long_word) == CharClass::Space {
+            idx += 1;
+        }
+
+        if idx < len {
+            let class = char_class(self.data.char(idx), long_word);
+            while idx + 1 < len && char_class(self.data.char(idx + 1), long_word) == class {
+                idx += 1;
+            }
+        }
+
+        self.set_cursor(idx.min(len.saturating_sub(1)));
+    }
+
     fn next_occurrence_offset(&self, search_text: &str, starting_at: usize) -> Option<usize> {
+        let starting_at = starting_at.min(self.line_count());
         self.data
-            .iter()
-            .skip(starting_at)
-            .position(|line| line.contains(search_text))
+            .lines_at(starting_at)
+            .position(|line| line.to_string().contains(search_text))
             .map(|base_offset| base_offset + starting_at)
     }
 
     fn append(&mut self, lines: Box<[String]>) {
-        let old_len = self.data.len();
+        let old_len = self.line_count();
+
+        let mut batch = String::new();
+        for line in Vec::from(lines) {
+            batch.push_str(&line);
+            batch.push('\n');
+        }
 
-        self.data.append(&mut Vec::from(lines));
+        let end = self.data.len_chars();
+        self.data.insert(end, &batch);
 
         if old_len + self.offset <= self.term.height() - STATUS_BAR_HEIGHT {
             self.dirty = true;
@@ -20,6 +20,15 @@ pub enum ViewCmd {
     Scroll(Dir),
     StartSearching,
     NextSearchResult,
+    ToggleLineNumbers,
+    SetMark,
+    JumpMark,
+    Digit(usize),
+    CursorLeft,
+    CursorRight,
+    WordForward(bool),
+    WordBack(bool),
+    WordEnd(bool),
     Noop,
 }
 
@@ -45,6 +54,18 @@ impl From<Key> for ViewCmd {
             Key::Ctrl('d') | Key::PageDown => ViewCmd::Scroll(Dir::HalfPageDown),
             Key::Ctrl('u') | Key::PageUp => ViewCmd::Scroll(Dir::HalfPageUp),
             Key::Char('/') => ViewCmd::StartSearching,
+            Key::Char('#') => ViewCmd::ToggleLineNumbers,
+            Key::Char('m') => ViewCmd::SetMark,
+            Key::Char('`') | Key::Char('\'') => ViewCmd::JumpMark,
+            Key::Char(c @ '0'..='9') => ViewCmd::Digit(c.to_digit(10).unwrap() as usize),
+            Key::Char('h') | Key::Left => ViewCmd::CursorLeft,
+            Key::Char('l') | Key::Right => ViewCmd::CursorRight,
+            Key::Char('w') => ViewCmd::WordForward(false),
+            Key::Char('W') => ViewCmd::WordForward(true),
+            Key::Char('b') => ViewCmd::WordBack(false),
+            Key::Char('B') => ViewCmd::WordBack(true),
+            Key::Char('e') => ViewCmd::WordEnd(false),
+            Key::Char('E') => ViewCmd::WordEnd(true),
             _ => ViewCmd::Noop,
         }
     }
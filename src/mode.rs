@@ -0,0 +1,11 @@
+//! Contains the `Mode` type, representing the current input mode of the viewer.
+
+/// The current mode the viewer is in, along with any state specific to that mode.
+pub enum Mode {
+    /// Viewing the file. Carries the active search term, if any, so matches
+    /// can stay highlighted while scrolling.
+    Viewing(Option<String>),
+
+    /// Typing a search query, not yet confirmed.
+    Searching(String),
+}
@@ -45,6 +45,11 @@ impl Term {
         self.write(cursor::Goto(x as u16, y as u16))
     }
 
+    /// Updates the terminal's known dimensions, e.g. after a `SIGWINCH`.
+    pub fn resize(&mut self, dimensions: (u16, u16)) {
+        self.dimensions = dimensions;
+    }
+
     pub fn show_cursor(&mut self) -> Result<()> {
         self.write(cursor::Show)
     }